@@ -0,0 +1,171 @@
+//! Encode an [`Image`] into a [BlurHash](https://blurha.sh) string — a tiny
+//! text placeholder (`components_x * components_y * 2 + 4` chars)
+//! suitable for painting a preview while the real image loads.
+
+use fimg::Image;
+
+const BASE83_CHARS: &[u8; 83] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+/// Encode `image` as a BlurHash string using `components_x * components_y`
+/// cosine basis functions (each in `1..=9`; `4x3` is a common choice).
+///
+/// Only the first 3 channels of each pixel are treated as RGB; a single
+/// channel (e.g. luminance) is broadcast to all three, and a 4th channel
+/// (alpha) is ignored, matching how [`blur`][crate::blur] stays
+/// channel-generic without caring what the channels mean.
+///
+/// # Panics
+/// Panics if `components_x` or `components_y` is not in `1..=9`.
+pub fn blurhash<const CHANNELS: usize, T: AsRef<[u8]>>(
+    image: &Image<T, CHANNELS>,
+    components_x: u32,
+    components_y: u32,
+) -> String {
+    assert!(
+        (1..=9).contains(&components_x),
+        "components_x must be 1..=9"
+    );
+    assert!(
+        (1..=9).contains(&components_y),
+        "components_y must be 1..=9"
+    );
+
+    let width = image.width() as usize;
+    let height = image.height() as usize;
+    let pixels: &[[u8; CHANNELS]] = unsafe {
+        std::slice::from_raw_parts(
+            image.buffer().as_ref().as_ptr().cast(),
+            image.len() / CHANNELS,
+        )
+    };
+
+    let mut factors = Vec::with_capacity((components_x * components_y) as usize);
+    for j in 0..components_y {
+        for i in 0..components_x {
+            factors.push(basis_factor(pixels, width, height, i, j));
+        }
+    }
+
+    let mut hash = String::new();
+    let size_flag = (components_x - 1) + (components_y - 1) * 9;
+    base83_push(&mut hash, size_flag, 1);
+
+    let dc = factors[0];
+    let ac = &factors[1..];
+
+    let actual_max = if let Some(max) = ac
+        .iter()
+        .flatten()
+        .map(|v| v.abs())
+        .fold(None, |m: Option<f32>, v| Some(m.map_or(v, |m| m.max(v))))
+    {
+        let quantised = ((max * 166.0 - 0.5).floor().clamp(0.0, 82.0)) as u32;
+        base83_push(&mut hash, quantised, 1);
+        (quantised + 1) as f32 / 166.0
+    } else {
+        base83_push(&mut hash, 0, 1);
+        1.0
+    };
+
+    base83_push(&mut hash, encode_dc(dc), 4);
+    for &factor in ac {
+        base83_push(&mut hash, encode_ac(factor, actual_max), 2);
+    }
+
+    hash
+}
+
+#[inline]
+fn pixel_rgb<const CHANNELS: usize>(px: [u8; CHANNELS]) -> [u8; 3] {
+    if CHANNELS >= 3 {
+        [px[0], px[1], px[2]]
+    } else {
+        [px[0], px[0], px[0]]
+    }
+}
+
+fn basis_factor<const CHANNELS: usize>(
+    pixels: &[[u8; CHANNELS]],
+    width: usize,
+    height: usize,
+    i: u32,
+    j: u32,
+) -> [f32; 3] {
+    let normalisation = if i == 0 && j == 0 { 1.0 } else { 2.0 };
+    let mut sum = [0.0f32; 3];
+
+    for py in 0..height {
+        for px in 0..width {
+            let basis = (std::f32::consts::PI * i as f32 * px as f32 / width as f32).cos()
+                * (std::f32::consts::PI * j as f32 * py as f32 / height as f32).cos();
+            let [r, g, b] = pixel_rgb(pixels[py * width + px]);
+            sum[0] += basis * srgb_to_linear(r);
+            sum[1] += basis * srgb_to_linear(g);
+            sum[2] += basis * srgb_to_linear(b);
+        }
+    }
+
+    let scale = normalisation / (width * height) as f32;
+    [sum[0] * scale, sum[1] * scale, sum[2] * scale]
+}
+
+#[inline]
+fn srgb_to_linear(value: u8) -> f32 {
+    let v = value as f32 / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[inline]
+fn linear_to_srgb(value: f32) -> u8 {
+    let v = value.clamp(0.0, 1.0);
+    let srgb = if v <= 0.003_130_8 {
+        v * 12.92 * 255.0 + 0.5
+    } else {
+        (1.055 * v.powf(1.0 / 2.4) - 0.055) * 255.0 + 0.5
+    };
+    srgb.clamp(0.0, 255.0) as u8
+}
+
+#[inline]
+fn sign_pow(value: f32, exp: f32) -> f32 {
+    value.signum() * value.abs().powf(exp)
+}
+
+fn encode_dc(color: [f32; 3]) -> u32 {
+    let [r, g, b] = color.map(linear_to_srgb);
+    (r as u32) << 16 | (g as u32) << 8 | b as u32
+}
+
+fn encode_ac(color: [f32; 3], actual_max: f32) -> u32 {
+    let [r, g, b] = color.map(|c| {
+        ((sign_pow(c / actual_max, 0.5) * 9.0 + 9.5)
+            .floor()
+            .clamp(0.0, 18.0)) as u32
+    });
+    r * 19 * 19 + g * 19 + b
+}
+
+fn base83_push(out: &mut String, value: u32, digits: u32) {
+    for i in (0..digits).rev() {
+        let digit = (value / 83u32.pow(i)) % 83;
+        out.push(BASE83_CHARS[digit as usize] as char);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fimg::Image;
+
+    #[test]
+    fn golden_hash_solid_color() {
+        let mut bytes = [200u8, 100, 50].repeat(16);
+        let hash = blurhash::<3, _>(&Image::build(4, 4).buf(&mut *bytes), 1, 1);
+        assert_eq!(hash, "00M|T9");
+    }
+}
@@ -2,8 +2,209 @@
 // the article in <http://blog.ivank.net/fastest-gaussian-blur.html>
 
 use std::cmp::min;
+use std::ops::{Add, Sub};
 use umath::FF32;
 
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// A pixel channel's scalar component type. Implemented for `u8` (the
+/// common byte-per-channel case), `u16` and `f32` (HDR / linear-light
+/// buffers, where clamping to 8 bits would destroy precision).
+///
+/// Provides the wider accumulator type the running box-blur sum is kept in,
+/// and the step from accumulator back down to a pixel component. `u8` is
+/// further specialized to route through [`crate::simd`] for RGB/RGBA.
+///
+/// `Send + Sync` is required so the `rayon` feature can hand
+/// `&[[Self; CHANNELS]]` across the row/column worker threads; every
+/// concrete impl here is a plain scalar, so these bounds (and `'static`)
+/// cost nothing.
+pub trait Primitive: Copy + Send + Sync + 'static {
+    /// Accumulator the running sum between add-next and subtract-trailing
+    /// is kept in, wide enough that it never overflows over the largest
+    /// practical blur radius.
+    type Acc: Copy + Add<Output = Self::Acc> + Sub<Output = Self::Acc>;
+
+    fn to_acc(self) -> Self::Acc;
+    fn acc_zero() -> Self::Acc;
+    /// `acc * iarr` (rounded for the integer component types), converted
+    /// back down to this component type.
+    fn average(acc: Self::Acc, iarr: f32) -> Self;
+
+    /// `vals[i] += add[i] - sub[i]`, then [`Self::average`] per channel.
+    #[inline]
+    fn step<const CHANNELS: usize>(
+        vals: &mut [Self::Acc; CHANNELS],
+        add: [Self; CHANNELS],
+        sub: [Self; CHANNELS],
+        iarr: f32,
+    ) -> [Self; CHANNELS] {
+        std::array::from_fn(|i| {
+            vals[i] = vals[i] + add[i].to_acc() - sub[i].to_acc();
+            Self::average(vals[i], iarr)
+        })
+    }
+}
+
+impl Primitive for u8 {
+    type Acc = isize;
+
+    #[inline]
+    fn to_acc(self) -> isize {
+        isize::from(self)
+    }
+
+    #[inline]
+    fn acc_zero() -> isize {
+        0
+    }
+
+    #[inline]
+    fn average(acc: isize, iarr: f32) -> u8 {
+        *round(FF32::new(acc as f32) * iarr) as u8
+    }
+
+    #[inline]
+    fn step<const CHANNELS: usize>(
+        vals: &mut [isize; CHANNELS],
+        add: [u8; CHANNELS],
+        sub: [u8; CHANNELS],
+        iarr: f32,
+    ) -> [u8; CHANNELS] {
+        if let Some(out) = crate::simd::accumulate_and_write(vals, add, sub, iarr) {
+            return out;
+        }
+        std::array::from_fn(|i| {
+            vals[i] += isize::from(add[i]) - isize::from(sub[i]);
+            u8::average(vals[i], iarr)
+        })
+    }
+}
+
+impl Primitive for u16 {
+    type Acc = i64;
+
+    #[inline]
+    fn to_acc(self) -> i64 {
+        i64::from(self)
+    }
+
+    #[inline]
+    fn acc_zero() -> i64 {
+        0
+    }
+
+    #[inline]
+    fn average(acc: i64, iarr: f32) -> u16 {
+        // Unlike `u8::average`, round in `f64`: the whole point of the wider
+        // `i64` accumulator is to not lose precision, and routing through
+        // `f32`'s 24-bit mantissa here would throw that away right before
+        // the final divide.
+        (acc as f64 * iarr as f64).round() as u16
+    }
+}
+
+impl Primitive for f32 {
+    // Floating-point pixels accumulate in `f64` and skip the integer
+    // `round` trick entirely - there's no fixed-point value to round to.
+    type Acc = f64;
+
+    #[inline]
+    fn to_acc(self) -> f64 {
+        self as f64
+    }
+
+    #[inline]
+    fn acc_zero() -> f64 {
+        0.0
+    }
+
+    #[inline]
+    fn average(acc: f64, iarr: f32) -> f32 {
+        (acc * iarr as f64) as f32
+    }
+}
+
+/// How the box blur samples past the edge of the image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeMode {
+    /// Repeat the first/last pixel of the row/column. The default; matches
+    /// the blur's original behavior.
+    #[default]
+    Clamp,
+    /// Mirror back into the row/column, so the pixel just past the edge
+    /// reads the pixel just before it.
+    Reflect,
+    /// Wrap around to the other side of the row/column, as for a tileable
+    /// texture.
+    Wrap,
+}
+
+/// The box-blur window for a single row/column pass: its radius, the
+/// radius's precomputed reciprocal (`1 / (2 * radius + 1)`, passed to
+/// [`Primitive::step`]/[`Primitive::average`]), and how it samples past the
+/// edge. Bundled together mainly to keep `box_blur_horz_row`/
+/// `box_blur_vert_col` under clippy's argument-count lint.
+#[derive(Clone, Copy)]
+struct BlurWindow {
+    radius: usize,
+    iarr: f32,
+    edge_mode: EdgeMode,
+}
+
+/// Map a (possibly out-of-range) position along a row/column back into
+/// `0..len` per `mode`. `Reflect` mirrors a single bounce off the edge it
+/// crossed, then clamps: for `len` much smaller than the overshoot this
+/// isn't a true periodic reflection, but the box blur never samples more
+/// than `blur_radius` past the edge, and a clamped mirror is still a sane
+/// answer in that regime.
+#[inline]
+fn edge_index(pos: isize, len: usize, mode: EdgeMode) -> usize {
+    let last = len as isize - 1;
+    match mode {
+        EdgeMode::Clamp => pos.clamp(0, last),
+        EdgeMode::Reflect => if pos < 0 {
+            -pos
+        } else if pos > last {
+            2 * last - pos
+        } else {
+            pos
+        }
+        .clamp(0, last),
+        EdgeMode::Wrap => pos.rem_euclid(len as isize),
+    }
+    .try_into()
+    .unwrap()
+}
+
+/// A `Copy` handle to a buffer that we know is safe to hand to other
+/// threads, used for [`box_blur_vert`]'s column pass: each parallel task
+/// only ever touches the (strided) column its loop index derives, and those
+/// columns never overlap. Unlike a plain `&mut` slice, [`Self::write`] never
+/// asserts exclusive access to anything but the single element it writes, so
+/// handing this to many threads at once doesn't claim overlapping exclusive
+/// access to the backing buffer the way reconstructing a `&mut` over the
+/// whole thing would.
+#[cfg(feature = "rayon")]
+#[derive(Clone, Copy)]
+struct SyncPtr<P, const CHANNELS: usize>(*mut [P; CHANNELS]);
+
+#[cfg(feature = "rayon")]
+unsafe impl<P, const CHANNELS: usize> Sync for SyncPtr<P, CHANNELS> {}
+
+#[cfg(feature = "rayon")]
+impl<P, const CHANNELS: usize> SyncPtr<P, CHANNELS> {
+    /// # Safety
+    /// `i` must be a valid, in-bounds offset into the buffer this pointer
+    /// was taken from, and no other thread may write (or hold a reference
+    /// to) that same offset concurrently.
+    #[inline]
+    unsafe fn write(self, i: usize, val: [P; CHANNELS]) {
+        self.0.add(i).write(val);
+    }
+}
+
 /// Blur an image slice of pixel arrays
 ///
 /// In-place blur image provided image pixel data, with any number of channels. Will make a single
@@ -19,18 +220,59 @@ use umath::FF32;
 /// # Safety
 ///
 /// fast math go brr, data must be width * height sized
-pub unsafe fn gaussian_blur<const CHANNELS: usize>(
-    data: &mut [[u8; CHANNELS]],
+pub unsafe fn gaussian_blur<P: Primitive, const CHANNELS: usize>(
+    data: &mut [[P; CHANNELS]],
     width: usize,
     height: usize,
     blur_radius: FF32,
 ) {
-    let boxes = create_box_gauss::<CHANNELS>(blur_radius);
+    gaussian_blur_xy(
+        data,
+        width,
+        height,
+        blur_radius,
+        blur_radius,
+        EdgeMode::default(),
+    );
+}
+
+/// Blur an image slice of pixel arrays with independent horizontal and
+/// vertical radii.
+///
+/// Like [`gaussian_blur`], but lets the horizontal and vertical box-blur
+/// schedules differ, for directional (e.g. motion-style) blur. Passing the
+/// same radius for both is equivalent to [`gaussian_blur`]; passing zero for
+/// one axis blurs only along the other, at the cost of that axis's pass
+/// being skipped via the existing zero-radius fast path in
+/// [`box_blur_horz`]/[`box_blur_vert`].
+///
+/// # Safety
+///
+/// fast math go brr, data must be width * height sized
+pub unsafe fn gaussian_blur_xy<P: Primitive, const CHANNELS: usize>(
+    data: &mut [[P; CHANNELS]],
+    width: usize,
+    height: usize,
+    blur_radius_horz: FF32,
+    blur_radius_vert: FF32,
+    edge_mode: EdgeMode,
+) {
+    let boxes_horz = create_box_gauss::<CHANNELS>(blur_radius_horz);
+    let boxes_vert = create_box_gauss::<CHANNELS>(blur_radius_vert);
     let mut backbuf = data.to_owned();
 
-    for &box_size in boxes.iter() {
-        let radius = ((box_size - 1) / 2) as usize;
-        box_blur(&mut backbuf, data, width, height, radius, radius);
+    for (&box_horz, &box_vert) in boxes_horz.iter().zip(boxes_vert.iter()) {
+        let radius_horz = ((box_horz - 1) / 2) as usize;
+        let radius_vert = ((box_vert - 1) / 2) as usize;
+        box_blur(
+            &mut backbuf,
+            data,
+            width,
+            height,
+            radius_horz,
+            radius_vert,
+            edge_mode,
+        );
     }
 }
 
@@ -72,16 +314,31 @@ unsafe fn create_box_gauss<const N: usize>(sigma: FF32) -> [i32; N] {
 }
 
 #[inline]
-fn box_blur<const CHANNELS: usize>(
-    backbuf: &mut [[u8; CHANNELS]],
-    frontbuf: &mut [[u8; CHANNELS]],
+fn box_blur<P: Primitive, const CHANNELS: usize>(
+    backbuf: &mut [[P; CHANNELS]],
+    frontbuf: &mut [[P; CHANNELS]],
     width: usize,
     height: usize,
     blur_radius_horz: usize,
     blur_radius_vert: usize,
+    edge_mode: EdgeMode,
 ) {
-    box_blur_horz(backbuf, frontbuf, width, height, blur_radius_horz);
-    box_blur_vert(frontbuf, backbuf, width, height, blur_radius_vert);
+    box_blur_horz(
+        backbuf,
+        frontbuf,
+        width,
+        height,
+        blur_radius_horz,
+        edge_mode,
+    );
+    box_blur_vert(
+        frontbuf,
+        backbuf,
+        width,
+        height,
+        blur_radius_vert,
+        edge_mode,
+    );
 }
 
 macro_rules! C {
@@ -100,211 +357,232 @@ macro_rules! C {
 }
 
 #[inline]
-fn box_blur_vert<const CHANNELS: usize>(
-    backbuf: &[[u8; CHANNELS]],
-    frontbuf: &mut [[u8; CHANNELS]],
+fn box_blur_vert<P: Primitive, const CHANNELS: usize>(
+    backbuf: &[[P; CHANNELS]],
+    frontbuf: &mut [[P; CHANNELS]],
     width: usize,
     height: usize,
     blur_radius: usize,
+    edge_mode: EdgeMode,
 ) {
     if blur_radius == 0 {
         frontbuf.copy_from_slice(backbuf);
         return;
     }
 
-    let iarr = 1.0 / (blur_radius + blur_radius + 1) as f32;
+    let window = BlurWindow {
+        radius: blur_radius,
+        iarr: 1.0 / (blur_radius + blur_radius + 1) as f32,
+        edge_mode,
+    };
 
+    #[cfg(feature = "rayon")]
+    {
+        let frontptr = SyncPtr(frontbuf.as_mut_ptr());
+        (0..width).into_par_iter().for_each(|i| {
+            box_blur_vert_col(
+                backbuf,
+                |ti, px| unsafe { frontptr.write(ti, px) },
+                width,
+                height,
+                window,
+                i,
+            );
+        });
+    }
+    #[cfg(not(feature = "rayon"))]
     for i in 0..width {
-        let col_start = i;
-        let col_end = i + width * (height - 1);
-        let mut ti: usize = i;
-        let mut li: usize = ti;
-        let mut ri: usize = ti + blur_radius * width;
+        box_blur_vert_col(backbuf, |ti, px| frontbuf[ti] = px, width, height, window, i);
+    }
+}
 
-        let fv: [u8; CHANNELS] = C!(backbuf[col_start]);
-        let lv: [u8; CHANNELS] = C!(backbuf[col_end]);
+/// Blur column `i` of `backbuf` into `write`. `write(ti, px)` must store `px`
+/// at index `ti` of an output buffer laid out like `backbuf` — it
+/// only ever receives indices within column `i`, so the rayon path can back
+/// it with a raw, per-element [`SyncPtr::write`] instead of a `&mut` slice
+/// spanning every column at once.
+#[inline]
+fn box_blur_vert_col<P: Primitive, const CHANNELS: usize>(
+    backbuf: &[[P; CHANNELS]],
+    mut write: impl FnMut(usize, [P; CHANNELS]),
+    width: usize,
+    height: usize,
+    window: BlurWindow,
+    i: usize,
+) {
+    let col_start = i;
+    let mut ti: usize = i;
+
+    // Sample the column at `pos` (relative to `col_start`, may be negative
+    // or past `height`), mapped back in range per `edge_mode`. In-bounds
+    // positions read straight through, since `edge_index` is the identity
+    // there - only the window's overhang past either end of the column
+    // needs this.
+    let get_v = |pos: isize| -> [P; CHANNELS] {
+        let idx = col_start + edge_index(pos, height, window.edge_mode) * width;
+        C! { backbuf[idx] }
+    };
 
-        let mut vals: [isize; CHANNELS] = [0; CHANNELS];
-        for i in 0..CHANNELS {
-            vals[i] = (blur_radius as isize + 1) * isize::from(fv[i]);
+    let r = window.radius as isize;
+    // Seed `vals` with the window for the (virtual) row just above the
+    // first one, so the loop below's first add/sub step produces the real
+    // window for row 0.
+    let mut vals: [P::Acc; CHANNELS] = [P::acc_zero(); CHANNELS];
+    for k in -(r + 1)..r {
+        let bb = get_v(k);
+        for c in 0..CHANNELS {
+            vals[c] = vals[c] + bb[c].to_acc();
         }
+    }
 
-        let get_top = |i: usize| {
-            if i < col_start {
-                fv
-            } else {
-                C! { backbuf[i] }
-            }
-        };
-
-        let get_bottom = |i: usize| {
-            if i > col_end {
-                lv
-            } else {
-                C! { backbuf[i] }
-            }
-        };
-
-        for j in 0..min(blur_radius, height) {
-            let bb = C! { backbuf[ti + j * width] };
-            for i in 0..CHANNELS {
-                vals[i] += isize::from(bb[i]);
-            }
-        }
-        if blur_radius > height {
-            for i in 0..CHANNELS {
-                vals[i] += (blur_radius - height) as isize * isize::from(lv[i]);
-            }
-        }
+    for _ in 0..min(height, window.radius + 1) {
+        let local = (ti - col_start) / width;
+        let add = get_v(local as isize + r);
+        let sub = get_v(local as isize - r - 1);
+        let out = P::step(&mut vals, add, sub, window.iarr);
+        write(ti, out);
+        ti += width;
+    }
 
-        for _ in 0..min(height, blur_radius + 1) {
-            let bb = get_bottom(ri);
+    if height > window.radius {
+        let mut ri = col_start + (window.radius + min(height, window.radius + 1)) * width;
+        let mut li = col_start;
+        for _ in (window.radius + 1)..(height - window.radius) {
+            let bb1 = C! { backbuf[ri] };
             ri += width;
-            for i in 0..CHANNELS {
-                vals[i] += isize::from(bb[i]) - isize::from(fv[i]);
-            }
+            let bb2 = C! { backbuf[li] };
+            li += width;
 
-            for i in 0..CHANNELS {
-                C! { frontbuf[ti][i] = *round(FF32::new(vals[i] as f32) * iarr) as u8 };
-            }
+            let out = P::step(&mut vals, bb1, bb2, window.iarr);
+            write(ti, out);
             ti += width;
         }
 
-        if height > blur_radius {
-            for _ in (blur_radius + 1)..(height - blur_radius) {
-                let bb1 = C! { backbuf[ri] };
-                ri += width;
-                let bb2 = C! { backbuf[li] };
-                li += width;
-
-                for i in 0..CHANNELS {
-                    vals[i] += isize::from(bb1[i]) - isize::from(bb2[i]);
-                }
-
-                for i in 0..CHANNELS {
-                    C! { frontbuf[ti][i] = *round(FF32::new(vals[i] as f32) * iarr) as u8 };
-                }
-                ti += width;
-            }
-
-            for _ in 0..min(height - blur_radius - 1, blur_radius) {
-                let bb = get_top(li);
-                li += width;
-
-                for i in 0..CHANNELS {
-                    vals[i] += isize::from(lv[i]) - isize::from(bb[i]);
-                }
+        for _ in 0..min(height - window.radius - 1, window.radius) {
+            let local = (ti - col_start) / width;
+            let add = get_v(local as isize + r);
+            let sub = C! { backbuf[li] };
+            li += width;
 
-                for i in 0..CHANNELS {
-                    C! { frontbuf[ti][i] = *round(FF32::new(vals[i] as f32) * iarr) as u8 };
-                }
-                ti += width;
-            }
+            let out = P::step(&mut vals, add, sub, window.iarr);
+            write(ti, out);
+            ti += width;
         }
     }
 }
 
 #[inline]
-fn box_blur_horz<const CHANNELS: usize>(
-    backbuf: &[[u8; CHANNELS]],
-    frontbuf: &mut [[u8; CHANNELS]],
+fn box_blur_horz<P: Primitive, const CHANNELS: usize>(
+    backbuf: &[[P; CHANNELS]],
+    frontbuf: &mut [[P; CHANNELS]],
     width: usize,
     height: usize,
     blur_radius: usize,
+    edge_mode: EdgeMode,
 ) {
+    debug_assert_eq!(frontbuf.len(), width * height);
+
     if blur_radius == 0 {
         frontbuf.copy_from_slice(backbuf);
         return;
     }
 
-    let iarr = 1.0 / (blur_radius + blur_radius + 1) as f32;
+    let window = BlurWindow {
+        radius: blur_radius,
+        iarr: 1.0 / (blur_radius + blur_radius + 1) as f32,
+        edge_mode,
+    };
 
-    for i in 0..height {
-        let row_start: usize = i * width;
-        let row_end: usize = i * width + width - 1;
-        let mut ti: usize = i * width;
-        let mut li: usize = ti;
-        let mut ri: usize = ti + blur_radius;
+    // A row is `width` contiguous elements, so (unlike the column pass in
+    // [`box_blur_vert`]) rayon's own `par_chunks_mut` can split `frontbuf`
+    // into genuinely disjoint `&mut` slices - no raw pointer or `SyncPtr`
+    // needed here.
+    #[cfg(feature = "rayon")]
+    frontbuf
+        .par_chunks_mut(width)
+        .enumerate()
+        .for_each(|(i, row)| {
+            box_blur_horz_row(backbuf, row, width, window, i);
+        });
+    #[cfg(not(feature = "rayon"))]
+    for (i, row) in frontbuf.chunks_mut(width).enumerate() {
+        box_blur_horz_row(backbuf, row, width, window, i);
+    }
+}
 
-        let fv: [u8; CHANNELS] = C! { backbuf[row_start] };
-        let lv: [u8; CHANNELS] = C! { backbuf[row_end] };
+/// Blur row `i` of `backbuf` into `frontbuf_row`, the `width`-long slice of
+/// just that row (not the whole image).
+#[inline]
+fn box_blur_horz_row<P: Primitive, const CHANNELS: usize>(
+    backbuf: &[[P; CHANNELS]],
+    frontbuf_row: &mut [[P; CHANNELS]],
+    width: usize,
+    window: BlurWindow,
+    i: usize,
+) {
+    let row_start: usize = i * width;
+    let mut ti: usize = 0;
+
+    // Sample the row at `pos` (relative to `row_start`, may be negative or
+    // past `width`), mapped back in range per `edge_mode`. In-bounds
+    // positions read straight through, since `edge_index` is the identity
+    // there - only the window's overhang past either end of the row needs
+    // this.
+    let get_h = |pos: isize| -> [P; CHANNELS] {
+        let idx = row_start + edge_index(pos, width, window.edge_mode);
+        C! { backbuf[idx] }
+    };
 
-        let mut vals: [isize; CHANNELS] = [0; CHANNELS];
-        for i in 0..CHANNELS {
-            vals[i] = (blur_radius as isize + 1) * isize::from(fv[i]);
+    let r = window.radius as isize;
+    // Seed `vals` with the window for the (virtual) column just left of
+    // the first one, so the loop below's first add/sub step produces the
+    // real window for column 0.
+    let mut vals: [P::Acc; CHANNELS] = [P::acc_zero(); CHANNELS];
+    for k in -(r + 1)..r {
+        let bb = get_h(k);
+        for c in 0..CHANNELS {
+            vals[c] = vals[c] + bb[c].to_acc();
         }
+    }
 
-        let get_left = |i: usize| {
-            if i < row_start {
-                fv
-            } else {
-                C! { backbuf[i] }
-            }
-        };
-
-        let get_right = |i: usize| {
-            if i > row_end {
-                lv
-            } else {
-                C! { backbuf[i] }
-            }
-        };
-
-        for j in 0..min(blur_radius, width) {
-            let bb = C! { backbuf[ti + j] };
-            for i in 0..CHANNELS {
-                vals[i] += isize::from(bb[i]);
-            }
-        }
-        if blur_radius > width {
-            for i in 0..CHANNELS {
-                vals[i] += (blur_radius - height) as isize * isize::from(lv[i]);
-            }
+    for _ in 0..min(width, window.radius + 1) {
+        let local = ti;
+        let add = get_h(local as isize + r);
+        let sub = get_h(local as isize - r - 1);
+        let out = P::step(&mut vals, add, sub, window.iarr);
+        for i in 0..CHANNELS {
+            C! { frontbuf_row[ti][i] = out[i] };
         }
+        ti += 1;
+    }
 
-        for _ in 0..min(width, blur_radius + 1) {
-            let bb = get_right(ri);
+    if width > window.radius {
+        let mut ri = window.radius + min(width, window.radius + 1);
+        let mut li = 0;
+        for _ in (window.radius + 1)..(width - window.radius) {
+            let bb1 = C! { backbuf[row_start + ri] };
             ri += 1;
-            for i in 0..CHANNELS {
-                vals[i] += isize::from(bb[i]) - isize::from(fv[i]);
-            }
+            let bb2 = C! { backbuf[row_start + li] };
+            li += 1;
 
+            let out = P::step(&mut vals, bb1, bb2, window.iarr);
             for i in 0..CHANNELS {
-                C! { frontbuf[ti][i] = *round(FF32::new(vals[i] as f32) * iarr) as u8 };
+                C! { frontbuf_row[ti][i] = out[i] };
             }
             ti += 1;
         }
 
-        if width > blur_radius {
-            for _ in (blur_radius + 1)..(width - blur_radius) {
-                let bb1 = C! { backbuf[ri] };
-                ri += 1;
-                let bb2 = C! { backbuf[li] };
-                li += 1;
-
-                for i in 0..CHANNELS {
-                    vals[i] += isize::from(bb1[i]) - isize::from(bb2[i]);
-                }
-
-                for i in 0..CHANNELS {
-                    C! { frontbuf[ti][i] = *round(FF32::new(vals[i] as f32) * iarr) as u8 };
-                }
-                ti += 1;
-            }
-
-            for _ in 0..min(width - blur_radius - 1, blur_radius) {
-                let bb = get_left(li);
-                li += 1;
-
-                for i in 0..CHANNELS {
-                    vals[i] += isize::from(lv[i]) - isize::from(bb[i]);
-                }
+        for _ in 0..min(width - window.radius - 1, window.radius) {
+            let local = ti;
+            let add = get_h(local as isize + r);
+            let sub = C! { backbuf[row_start + li] };
+            li += 1;
 
-                for i in 0..CHANNELS {
-                    C! { frontbuf[ti][i] = *round(FF32::new(vals[i] as f32) * iarr) as u8 };
-                }
-                ti += 1;
+            let out = P::step(&mut vals, add, sub, window.iarr);
+            for i in 0..CHANNELS {
+                C! { frontbuf_row[ti][i] = out[i] };
             }
+            ti += 1;
         }
     }
 }
@@ -316,3 +594,224 @@ fn round(mut x: FF32) -> FF32 {
     x -= 12582912.0;
     x
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blur_row(row: &[u8], radius: usize, edge_mode: EdgeMode) -> Vec<u8> {
+        let width = row.len();
+        let backbuf: Vec<[u8; 1]> = row.iter().map(|&b| [b]).collect();
+        let mut frontbuf = vec![[0u8; 1]; width];
+        box_blur_horz(&backbuf, &mut frontbuf, width, 1, radius, edge_mode);
+        frontbuf.into_iter().map(|[b]| b).collect()
+    }
+
+    fn blur_col(col: &[u8], radius: usize, edge_mode: EdgeMode) -> Vec<u8> {
+        let height = col.len();
+        let backbuf: Vec<[u8; 1]> = col.iter().map(|&b| [b]).collect();
+        let mut frontbuf = vec![[0u8; 1]; height];
+        box_blur_vert(&backbuf, &mut frontbuf, 1, height, radius, edge_mode);
+        frontbuf.into_iter().map(|[b]| b).collect()
+    }
+
+    // Regression test: Reflect/Wrap used to be silently identical to Clamp
+    // for any radius under roughly half the row/column length, since the
+    // near-edge window update hardcoded the first/last pixel instead of
+    // routing through the edge-mode-aware sampler.
+    #[test]
+    fn edge_mode_changes_output_at_a_normal_radius() {
+        let data = [10u8, 200, 30, 4, 250, 60, 7, 8, 90, 100];
+        let radius = 2; // well under half of `data.len()`
+
+        let clamp = blur_row(&data, radius, EdgeMode::Clamp);
+        assert_ne!(clamp, blur_row(&data, radius, EdgeMode::Reflect));
+        assert_ne!(clamp, blur_row(&data, radius, EdgeMode::Wrap));
+
+        let clamp = blur_col(&data, radius, EdgeMode::Clamp);
+        assert_ne!(clamp, blur_col(&data, radius, EdgeMode::Reflect));
+        assert_ne!(clamp, blur_col(&data, radius, EdgeMode::Wrap));
+    }
+
+    // Clamp is the default and must keep matching the blur's original,
+    // pre-`EdgeMode` behavior: a straightforward clamped window sum.
+    #[test]
+    fn clamp_matches_naive_reference() {
+        let data = [10u8, 200, 30, 4, 250, 60, 7, 8, 90, 100];
+        for radius in 1..=15 {
+            let iarr = 1.0 / (radius as f32 * 2.0 + 1.0);
+            let naive: Vec<u8> = (0..data.len() as isize)
+                .map(|ti| {
+                    let sum: i64 = (ti - radius as isize..=ti + radius as isize)
+                        .map(|p| i64::from(data[p.clamp(0, data.len() as isize - 1) as usize]))
+                        .sum();
+                    (sum as f32 * iarr).round() as u8
+                })
+                .collect();
+            assert_eq!(naive, blur_row(&data, radius, EdgeMode::Clamp));
+        }
+    }
+
+    // Regression test for `00f01eb`: the horizontal pass once padded its seed
+    // sum using `height` instead of `width`, which a width == height strip
+    // (or the single-row/column helpers above) can never catch. Use a real
+    // `width != height` image and check both passes against a naive
+    // clamped-window reference computed axis by axis.
+    #[test]
+    fn box_blur_2d_width_ne_height_matches_naive_reference() {
+        let width = 5;
+        let height = 3;
+        let data: [u8; 15] = [
+            10, 200, 30, 4, 250, //
+            60, 7, 8, 90, 100, //
+            5, 6, 7, 8, 9,
+        ];
+        let radius = 2;
+        let iarr = 1.0 / (radius as f32 * 2.0 + 1.0);
+
+        let backbuf: Vec<[u8; 1]> = data.iter().map(|&b| [b]).collect();
+        let mut midbuf = vec![[0u8; 1]; width * height];
+        box_blur_horz(&backbuf, &mut midbuf, width, height, radius, EdgeMode::Clamp);
+        let mut frontbuf = vec![[0u8; 1]; width * height];
+        box_blur_vert(&midbuf, &mut frontbuf, width, height, radius, EdgeMode::Clamp);
+        let got: Vec<u8> = frontbuf.into_iter().map(|[b]| b).collect();
+
+        let window_sum = |samples: &[u8], i: usize, len: usize| -> u8 {
+            let sum: i64 = (i as isize - radius as isize..=i as isize + radius as isize)
+                .map(|p| i64::from(samples[p.clamp(0, len as isize - 1) as usize]))
+                .sum();
+            (sum as f32 * iarr).round() as u8
+        };
+
+        let mid_naive: Vec<u8> = (0..height)
+            .flat_map(|y| {
+                let row = &data[y * width..(y + 1) * width];
+                (0..width).map(move |x| window_sum(row, x, width))
+            })
+            .collect();
+        let naive: Vec<u8> = (0..height)
+            .flat_map(|y| {
+                let mid_naive = mid_naive.clone();
+                (0..width).map(move |x| {
+                    let col: Vec<u8> = (0..height).map(|yy| mid_naive[yy * width + x]).collect();
+                    window_sum(&col, y, height)
+                })
+            })
+            .collect();
+
+        assert_eq!(naive, got);
+    }
+
+    // The vectorized `step_sse2`/`step_neon` paths in `crate::simd` only
+    // kick in for 3- and 4-channel `u8` rows/columns (see
+    // `accumulate_and_write`); every other test here runs single-channel
+    // data and so never touches them. Check that a 3/4-lane row or column
+    // blurred together matches each channel blurred independently through
+    // the single-channel (scalar) path, so a transcription error in
+    // `widen`/`widen_u8` or a rounding mismatch between the vector and
+    // scalar paths would show up as a failing assertion here.
+    fn check_row<const N: usize>(pixels: &[[u8; N]], radius: usize) {
+        let width = pixels.len();
+        let mut frontbuf = vec![[0u8; N]; width];
+        box_blur_horz(pixels, &mut frontbuf, width, 1, radius, EdgeMode::Clamp);
+
+        for c in 0..N {
+            let channel: Vec<u8> = pixels.iter().map(|p| p[c]).collect();
+            let expected = blur_row(&channel, radius, EdgeMode::Clamp);
+            let got: Vec<u8> = frontbuf.iter().map(|p| p[c]).collect();
+            assert_eq!(expected, got, "channel {c} of {N} mismatched for row radius {radius}");
+        }
+    }
+
+    fn check_col<const N: usize>(pixels: &[[u8; N]], radius: usize) {
+        let height = pixels.len();
+        let mut frontbuf = vec![[0u8; N]; height];
+        box_blur_vert(pixels, &mut frontbuf, 1, height, radius, EdgeMode::Clamp);
+
+        for c in 0..N {
+            let channel: Vec<u8> = pixels.iter().map(|p| p[c]).collect();
+            let expected = blur_col(&channel, radius, EdgeMode::Clamp);
+            let got: Vec<u8> = frontbuf.iter().map(|p| p[c]).collect();
+            assert_eq!(expected, got, "channel {c} of {N} mismatched for col radius {radius}");
+        }
+    }
+
+    #[test]
+    fn simd_path_matches_scalar_per_channel() {
+        let rgb: [[u8; 3]; 10] = [
+            [10, 1, 240],
+            [200, 2, 3],
+            [30, 250, 4],
+            [4, 5, 6],
+            [250, 7, 8],
+            [60, 9, 10],
+            [7, 11, 250],
+            [8, 12, 13],
+            [90, 14, 15],
+            [100, 16, 17],
+        ];
+        let rgba: [[u8; 4]; 10] = [
+            [10, 1, 240, 255],
+            [200, 2, 3, 128],
+            [30, 250, 4, 0],
+            [4, 5, 6, 64],
+            [250, 7, 8, 200],
+            [60, 9, 10, 32],
+            [7, 11, 250, 16],
+            [8, 12, 13, 8],
+            [90, 14, 15, 250],
+            [100, 16, 17, 99],
+        ];
+
+        for radius in [1, 2, 4] {
+            check_row(&rgb, radius);
+            check_col(&rgb, radius);
+            check_row(&rgba, radius);
+            check_col(&rgba, radius);
+        }
+    }
+
+    // `box_blur_horz`/`box_blur_vert` dispatch their row/column loop through
+    // rayon under this feature (`par_chunks_mut` for rows, `SyncPtr::write`
+    // for the strided column pass). Drive `box_blur_horz_row`/
+    // `box_blur_vert_col` directly in a plain sequential loop as a reference
+    // and check it against the rayon-dispatched wrappers on a real 2D
+    // buffer, so a mistake in the disjoint-region reasoning behind
+    // `unsafe impl Sync for SyncPtr` would show up as a mismatch here
+    // instead of silent data corruption.
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn rayon_path_matches_sequential_row_col() {
+        let width = 6;
+        let height = 4;
+        let data: [u8; 24] = [
+            10, 200, 30, 4, 250, 60, //
+            7, 8, 90, 100, 5, 6, //
+            7, 8, 9, 11, 12, 13, //
+            14, 15, 16, 17, 18, 19, //
+        ];
+        let radius = 2;
+        let window = BlurWindow {
+            radius,
+            iarr: 1.0 / (radius as f32 * 2.0 + 1.0),
+            edge_mode: EdgeMode::Clamp,
+        };
+        let backbuf: Vec<[u8; 1]> = data.iter().map(|&b| [b]).collect();
+
+        let mut seq_mid = vec![[0u8; 1]; width * height];
+        for (i, row) in seq_mid.chunks_mut(width).enumerate() {
+            box_blur_horz_row(&backbuf, row, width, window, i);
+        }
+        let mut seq_front = vec![[0u8; 1]; width * height];
+        for i in 0..width {
+            box_blur_vert_col(&seq_mid, |ti, px| seq_front[ti] = px, width, height, window, i);
+        }
+
+        let mut par_mid = vec![[0u8; 1]; width * height];
+        box_blur_horz(&backbuf, &mut par_mid, width, height, radius, EdgeMode::Clamp);
+        let mut par_front = vec![[0u8; 1]; width * height];
+        box_blur_vert(&par_mid, &mut par_front, width, height, radius, EdgeMode::Clamp);
+
+        assert_eq!(seq_front, par_front);
+    }
+}
@@ -1,5 +1,10 @@
 #![doc = include_str!("../README.md")]
+mod blurhash;
 mod fastblur;
+mod simd;
+
+pub use blurhash::blurhash;
+pub use fastblur::{EdgeMode, Primitive};
 
 use fimg::Image;
 use umath::FF32;
@@ -8,6 +13,21 @@ use umath::FF32;
 pub fn blur<const CHANNELS: usize, T: AsRef<[u8]> + AsMut<[u8]>>(
     image: &mut Image<T, CHANNELS>,
     radius: FF32,
+) {
+    blur_xy(image, radius, radius, EdgeMode::default());
+}
+
+/// Blur a image with independent horizontal and vertical radii, for
+/// directional (e.g. motion-style) blur. Pass the same radius for both axes
+/// to get the same result as [`blur`].
+///
+/// `edge_mode` controls how the blur samples past the image's border; see
+/// [`EdgeMode`]. [`EdgeMode::Clamp`] matches [`blur`].
+pub fn blur_xy<const CHANNELS: usize, T: AsRef<[u8]> + AsMut<[u8]>>(
+    image: &mut Image<T, CHANNELS>,
+    radius_x: FF32,
+    radius_y: FF32,
+    edge_mode: EdgeMode,
 ) {
     let pixels: &mut [[u8; CHANNELS]] = unsafe {
         std::slice::from_raw_parts_mut(
@@ -16,11 +36,30 @@ pub fn blur<const CHANNELS: usize, T: AsRef<[u8]> + AsMut<[u8]>>(
         )
     };
     unsafe {
-        fastblur::gaussian_blur(
+        fastblur::gaussian_blur_xy(
             pixels,
             image.width() as usize,
             image.height() as usize,
-            radius,
+            radius_x,
+            radius_y,
+            edge_mode,
         )
     };
 }
+
+/// Blur a raw slice of pixels directly, for component types [`Image`]
+/// cannot back (it only stores `u8`). Generic over [`Primitive`] — `u8`
+/// behaves exactly like [`blur`]; `u16`/`f32` are for HDR / linear-light
+/// buffers where clamping to 8 bits would destroy precision.
+///
+/// # Safety
+///
+/// fast math go brr, `data` must be `width * height` sized.
+pub unsafe fn blur_pixels<P: Primitive, const CHANNELS: usize>(
+    data: &mut [[P; CHANNELS]],
+    width: usize,
+    height: usize,
+    radius: FF32,
+) {
+    fastblur::gaussian_blur(data, width, height, radius)
+}